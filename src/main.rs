@@ -1,12 +1,23 @@
+mod storage;
+
+use storage::{
+    FileStore,
+    ObjectStore,
+    StorageBackend,
+    StorageError,
+};
+
 use gofile_api::{
     Api,
     Content,
     ContentKind,
+    ServerApi,
 };
 
 use clap::{
     Parser,
     Subcommand,
+    ValueEnum,
 };
 
 use url::{
@@ -20,6 +31,9 @@ use uuid::{
 use std::{
     error,
     io,
+    future::{
+        Future,
+    },
     pin::{
         Pin,
     },
@@ -33,26 +47,43 @@ use std::{
         Formatter,
     },
     path::{
+        Path,
         PathBuf,
     },
     task::{
         Poll,
         Context,
     },
+    time::{
+        Duration,
+    },
 };
 
 use futures::{
     TryStreamExt,
+    stream::{
+        self,
+        StreamExt,
+    },
     io::{
         AsyncWrite,
+        AsyncWriteExt,
     },
 };
 
 use tokio::{
     fs::{
         File,
+        OpenOptions,
+        create_dir_all,
         metadata,
+        read_dir,
+        rename,
     },
+    io::{
+        AsyncReadExt,
+    },
+    time::sleep,
 };
 
 use tokio_util::{
@@ -61,8 +92,19 @@ use tokio_util::{
     },
 };
 
+use indicatif::{
+    MultiProgress,
+    ProgressBar,
+    ProgressStyle,
+};
+
 use md5;
 
+use sha2::{
+    Sha256,
+    Digest as _,
+};
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
@@ -75,6 +117,21 @@ enum Command {
     Download {
         #[arg(value_parser = ContentId::parse_content_id)]
         content_id: ContentId,
+
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+
+        #[arg(long, default_value = ".")]
+        output_dir: PathBuf,
+
+        #[arg(long, default_value_t = 5)]
+        max_retries: u32,
+
+        #[arg(long)]
+        store: Option<String>,
+
+        #[arg(long = "checksum", value_enum)]
+        checksums: Vec<ChecksumAlgorithm>,
     },
     Upload {
         #[arg()]
@@ -82,6 +139,9 @@ enum Command {
 
         #[arg(long)]
         public: bool,
+
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
     },
 }
 
@@ -100,8 +160,22 @@ enum Error {
     FileCouldntBeCreated(String),
     FileCouldntBeWritten(String),
     CouldntReadMetadata(String),
-    NotAFile(PathBuf),
     Md5DigestMismatched(String),
+    DirectoryCouldntBeCreated(String),
+    FileCouldntBeRenamed(String),
+    NonResumableStatus(u16),
+    RetriesExhausted(Box<Error>),
+    CouldntReadDirectory(String),
+    NotAFileOrDirectory(PathBuf),
+    UnsafeContentName(String),
+    InvalidStoreSpec(String),
+    StorageError(StorageError),
+}
+
+impl From<StorageError> for Error {
+    fn from(err: StorageError) -> Self {
+        Self::StorageError(err)
+    }
 }
 
 impl Display for Error {
@@ -134,6 +208,19 @@ impl From<reqwest::Error> for Error {
     }
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ChecksumAlgorithm {
+    Sha256,
+}
+
+impl ChecksumAlgorithm {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 enum ContentId {
     DownloadUrl(Url, String),
@@ -191,36 +278,69 @@ impl ContentId {
 async fn main() -> Result<(), Error> {
     let args = Cli::parse();
     match args.command {
-        Command::Download { content_id } => {
-            download(content_id).await
+        Command::Download { content_id, concurrency, output_dir, max_retries, store, checksums } => {
+            download(content_id, concurrency, output_dir, max_retries, store, checksums).await
         },
-        Command::Upload { path, public } => {
-            upload(path, public).await
+        Command::Upload { path, public, concurrency } => {
+            upload(path, public, concurrency).await
         },
     }
 }
 
-async fn download(content_id: ContentId) -> Result<(), Error> {
+async fn download(content_id: ContentId, concurrency: usize, output_dir: PathBuf, max_retries: u32, store: Option<String>, checksums: Vec<ChecksumAlgorithm>) -> Result<(), Error> {
     let api = Api::new();
     let token = get_token()?;
     let api = api.authorize(&token);
+
+    let store: Option<Box<dyn StorageBackend>> = match store {
+        Some(spec) => Some(parse_store(&spec).await?),
+        None => None,
+    };
+    let store = store.as_deref();
+
     match content_id {
         ContentId::DownloadUrl(url, filename) => {
-            let _ = download_impl(url, filename, &token).await?;
+            if let Err(err) = create_dir_all(&output_dir).await {
+                return Err(Error::DirectoryCouldntBeCreated(format!("{}", err)));
+            };
+            let multi_progress = MultiProgress::new();
+            let path = safe_child_path(&output_dir, &filename)?;
+            let digests = download_impl(url, path.clone(), &token, &multi_progress, max_retries, store, &checksums).await?;
+            for (algorithm, hex_digest) in &digests.others {
+                println!("{} {}  {}", algorithm, hex_digest, path.display());
+            };
             Ok(())
         },
         ContentId::Uuid(id) => {
             let content = api.get_content_by_id(id).await?;
-            download_all_child_contents(content, &token).await
+            let dir = safe_child_path(&output_dir, &content.name)?;
+            download_all_child_contents(&api, content, &token, concurrency, &dir, max_retries, store, &checksums).await
         },
         ContentId::Code(code) => {
             let content = api.get_content_by_code(code).await?;
-            download_all_child_contents(content, &token).await
+            let dir = safe_child_path(&output_dir, &content.name)?;
+            download_all_child_contents(&api, content, &token, concurrency, &dir, max_retries, store, &checksums).await
         },
     }
 }
 
-async fn upload(path: PathBuf, public: bool) -> Result<(), Error> {
+/// Parses `--store` specs of the form `s3://bucket/prefix`. Local downloads
+/// (no `--store`) keep using the resumable file-based path below unchanged.
+async fn parse_store(spec: &str) -> Result<Box<dyn StorageBackend>, Error> {
+    if let Some(rest) = spec.strip_prefix("s3://") {
+        let (bucket, prefix) = match rest.split_once('/') {
+            Some((bucket, prefix)) => (bucket.to_string(), prefix.to_string()),
+            None => (rest.to_string(), String::new()),
+        };
+        return Ok(Box::new(ObjectStore::new(bucket, prefix).await));
+    };
+    if let Some(rest) = spec.strip_prefix("file://") {
+        return Ok(Box::new(FileStore::new(PathBuf::from(rest))));
+    };
+    Err(Error::InvalidStoreSpec(spec.to_string()))
+}
+
+async fn upload(path: PathBuf, public: bool, concurrency: usize) -> Result<(), Error> {
     let api = Api::new();
     let token = get_token()?;
     let api = api.authorize(&token);
@@ -230,14 +350,21 @@ async fn upload(path: PathBuf, public: bool) -> Result<(), Error> {
         Err(err) => return Err(Error::CouldntReadMetadata(format!("{}", err))),
     };
 
-    if !metadata.is_file() {
-        return Err(Error::NotAFile(path))
-    };
-
     let server_api = api.get_server().await?;
-    let uploaded_file_info = server_api.upload_file(path).await?;
 
-    let content_id = uploaded_file_info.parent_folder;
+    let (content_id, download_page) = if metadata.is_dir() {
+        let folder_name = path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("."));
+        let folder = api.create_folder(None, &folder_name).await?;
+        upload_dir_contents(&api, &server_api, &path, folder.id, concurrency).await?;
+        (folder.id, folder.download_page)
+    } else if metadata.is_file() {
+        let uploaded_file_info = server_api.upload_file(path, None).await?;
+        (uploaded_file_info.parent_folder, uploaded_file_info.download_page)
+    } else {
+        return Err(Error::NotAFileOrDirectory(path));
+    };
 
     if public {
         api.set_public_option(content_id, true).await?;
@@ -245,50 +372,321 @@ async fn upload(path: PathBuf, public: bool) -> Result<(), Error> {
         api.set_public_option(content_id, false).await?;
     }
 
-    println!("{}", uploaded_file_info.download_page);
+    println!("{}", download_page);
 
     Ok(())
 }
 
-async fn download_impl(url: Url, filename: String, token: &str) -> Result<md5::Digest, Error> {
+fn upload_dir_contents<'a>(
+    api: &'a Api,
+    server_api: &'a ServerApi,
+    dir: &'a Path,
+    parent_folder_id: Uuid,
+    concurrency: usize,
+) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+    Box::pin(async move {
+        let mut read_dir = match read_dir(dir).await {
+            Ok(read_dir) => read_dir,
+            Err(err) => return Err(Error::CouldntReadDirectory(format!("{}", err))),
+        };
+
+        let mut entries = Vec::new();
+        loop {
+            match read_dir.next_entry().await {
+                Ok(Some(entry)) => entries.push(entry.path()),
+                Ok(None) => break,
+                Err(err) => return Err(Error::CouldntReadDirectory(format!("{}", err))),
+            };
+        };
+
+        let results: Vec<Result<(), Error>> = stream::iter(entries)
+            .map(|entry_path| {
+                async move {
+                    let metadata = match metadata(&entry_path).await {
+                        Ok(metadata) => metadata,
+                        Err(err) => return Err(Error::CouldntReadMetadata(format!("{}", err))),
+                    };
+
+                    if metadata.is_dir() {
+                        let folder_name = entry_path.file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_else(|| String::from("."));
+                        let folder = api.create_folder(Some(parent_folder_id), &folder_name).await?;
+                        upload_dir_contents(api, server_api, &entry_path, folder.id, concurrency).await
+                    } else if metadata.is_file() {
+                        let _ = server_api.upload_file(entry_path, Some(parent_folder_id)).await?;
+                        Ok(())
+                    } else {
+                        Ok(())
+                    }
+                }
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+
+        for result in results {
+            result?;
+        };
+        Ok(())
+    })
+}
+
+async fn download_impl(url: Url, path: PathBuf, token: &str, multi_progress: &MultiProgress, max_retries: u32, store: Option<&dyn StorageBackend>, checksums: &[ChecksumAlgorithm]) -> Result<Digests, Error> {
+    if let Some(store) = store {
+        return download_impl_to_backend(url, path, token, multi_progress, max_retries, store, checksums).await;
+    };
+
+    let tmp_path = tmp_path(&path);
+    let mut delay = Duration::from_secs(1);
+
+    for attempt in 0.. {
+        match download_attempt(&url, &tmp_path, token, multi_progress, checksums).await {
+            Ok(digests) => {
+                return match rename(&tmp_path, &path).await {
+                    Ok(()) => Ok(digests),
+                    Err(err) => Err(Error::FileCouldntBeRenamed(format!("{}", err))),
+                };
+            },
+            Err(err @ Error::NonResumableStatus(_)) => return Err(err),
+            Err(err) => {
+                if attempt >= max_retries {
+                    return Err(Error::RetriesExhausted(Box::new(err)));
+                };
+                sleep(delay).await;
+                delay *= 2;
+            },
+        }
+    };
+    unreachable!()
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut tmp = path.as_os_str().to_os_string();
+    tmp.push(".tmp");
+    PathBuf::from(tmp)
+}
+
+/// Joins a single path component coming from gofile (a content name, or a
+/// download-URL filename) onto `dir`, rejecting anything that could escape
+/// it: empty names, `.`/`..`, path separators, and absolute paths.
+fn safe_child_path(dir: &Path, name: &str) -> Result<PathBuf, Error> {
+    if name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.contains('/')
+        || name.contains('\\')
+        || Path::new(name).is_absolute()
+    {
+        return Err(Error::UnsafeContentName(name.to_string()));
+    };
+    Ok(dir.join(name))
+}
+
+async fn download_attempt(url: &Url, tmp_path: &Path, token: &str, multi_progress: &MultiProgress, checksums: &[ChecksumAlgorithm]) -> Result<Digests, Error> {
+    let resume_offset = match metadata(tmp_path).await {
+        Ok(metadata) => Some(metadata.len()),
+        Err(_) => None,
+    };
+
     let client = reqwest::Client::new();
-    let res = client.get(url)
+    let mut request = client.get(url.clone())
+        .header("Cookie", format!("accountToken={}", token));
+    if let Some(offset) = resume_offset {
+        request = request.header("Range", format!("bytes={}-", offset));
+    };
+    let res = request.send().await?;
+
+    let (md5_cx, hasher_states, file, offset) = match (resume_offset, res.status()) {
+        (Some(offset), reqwest::StatusCode::PARTIAL_CONTENT) => {
+            let (md5_cx, hasher_states) = hash_states_for_existing_bytes(tmp_path, checksums).await?;
+            let file = match OpenOptions::new().append(true).open(tmp_path).await {
+                Ok(file) => file.compat(),
+                Err(err) => return Err(Error::FileCouldntBeCreated(format!("{}", err))),
+            };
+            (md5_cx, hasher_states, file, offset)
+        },
+        (None, reqwest::StatusCode::OK) => {
+            let file = match File::create(tmp_path).await {
+                Ok(file) => file.compat(),
+                Err(err) => return Err(Error::FileCouldntBeCreated(format!("{}", err))),
+            };
+            let hasher_states = checksums.iter().map(|algorithm| (algorithm.name(), HasherState::new(*algorithm))).collect();
+            (md5::Context::new(), hasher_states, file, 0)
+        },
+        (_, status) => return Err(Error::NonResumableStatus(status.as_u16())),
+    };
+
+    let message = tmp_path.display().to_string();
+    let total_len = offset + res.content_length().unwrap_or(0);
+    let progress_bar = multi_progress.add(ProgressBar::new(total_len));
+    progress_bar.set_style(progress_bar_style());
+    progress_bar.set_message(message.clone());
+    progress_bar.set_position(offset);
+
+    let mut byte_stream = res.bytes_stream().map_err(|err| io::Error::new(io::ErrorKind::Other, err)).into_async_read();
+    let file = ProgressFilter::new(file, progress_bar.clone());
+    let mut file = HashingWriter::new_with_others(file, md5_cx, hasher_states);
+    match futures::io::copy(&mut byte_stream, &mut file).await {
+        Err(err) => Err(Error::FileCouldntBeWritten(format!("{}", err))),
+        Ok(_) => {
+            if let Err(err) = file.close().await {
+                return Err(Error::FileCouldntBeWritten(format!("{}", err)));
+            };
+            progress_bar.finish_with_message(message);
+            Ok(file.compute_digests())
+        },
+    }
+}
+
+/// Same retry/backoff shape as `download_impl`, but writes through a
+/// `StorageBackend` instead of a local `.tmp` file. Object stores don't get
+/// the `Range`-based resume above: each retry re-downloads the whole file.
+async fn download_impl_to_backend(url: Url, path: PathBuf, token: &str, multi_progress: &MultiProgress, max_retries: u32, store: &dyn StorageBackend, checksums: &[ChecksumAlgorithm]) -> Result<Digests, Error> {
+    let mut delay = Duration::from_secs(1);
+
+    for attempt in 0.. {
+        match download_attempt_to_backend(&url, &path, token, multi_progress, store, checksums).await {
+            Ok(digests) => {
+                store.finalize(&path).await?;
+                return Ok(digests);
+            },
+            Err(err) => {
+                if attempt >= max_retries {
+                    return Err(Error::RetriesExhausted(Box::new(err)));
+                };
+                sleep(delay).await;
+                delay *= 2;
+            },
+        }
+    };
+    unreachable!()
+}
+
+async fn download_attempt_to_backend(url: &Url, path: &Path, token: &str, multi_progress: &MultiProgress, store: &dyn StorageBackend, checksums: &[ChecksumAlgorithm]) -> Result<Digests, Error> {
+    let client = reqwest::Client::new();
+    let res = client.get(url.clone())
         .header("Cookie", format!("accountToken={}", token))
         .send()
         .await?;
+
+    let message = path.display().to_string();
+    let progress_bar = multi_progress.add(ProgressBar::new(res.content_length().unwrap_or(0)));
+    progress_bar.set_style(progress_bar_style());
+    progress_bar.set_message(message.clone());
+
     let mut byte_stream = res.bytes_stream().map_err(|err| io::Error::new(io::ErrorKind::Other, err)).into_async_read();
-    let file = match File::create(filename).await {
-        Ok(file) => file.compat(),
-        Err(err) => return Err(Error::FileCouldntBeCreated(format!("{}", err))),
-    };
-    let mut file = Md5Filter::new(file);
-    match futures::io::copy(&mut byte_stream, &mut file).await {
+    let writer = store.create(path).await?;
+    let writer = ProgressFilter::new(writer, progress_bar.clone());
+    let mut writer = HashingWriter::new(writer, checksums);
+    match futures::io::copy(&mut byte_stream, &mut writer).await {
         Err(err) => Err(Error::FileCouldntBeWritten(format!("{}", err))),
-        Ok(_) => Ok(file.compute_digest()),
+        Ok(_) => {
+            if let Err(err) = writer.close().await {
+                return Err(Error::FileCouldntBeWritten(format!("{}", err)));
+            };
+            progress_bar.finish_with_message(message);
+            Ok(writer.compute_digests())
+        },
     }
 }
 
-async fn download_all_child_contents(content: Content, token: &str) -> Result<(), Error> {
-    let ContentKind::Folder { contents, .. } = content.kind else {
-        return Err(Error::InvalidTopLevelFile(content.name));
-    };
-    let Some(contents) = contents else {
-        return Err(Error::NoContent);
+/// Replays the bytes already on disk through a fresh md5 context *and* every
+/// requested `--checksum` hasher, so a retry-driven resume reports digests
+/// over the whole file rather than just the bytes written after the resume
+/// offset.
+async fn hash_states_for_existing_bytes(tmp_path: &Path, checksums: &[ChecksumAlgorithm]) -> Result<(md5::Context, Vec<(&'static str, HasherState)>), Error> {
+    let mut md5_cx = md5::Context::new();
+    let mut hasher_states: Vec<(&'static str, HasherState)> = checksums.iter()
+        .map(|algorithm| (algorithm.name(), HasherState::new(*algorithm)))
+        .collect();
+    let mut existing = match File::open(tmp_path).await {
+        Ok(file) => file,
+        Err(err) => return Err(Error::CouldntReadMetadata(format!("{}", err))),
     };
-    if 0 == contents.len() {
-        return Err(Error::NoContent);
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = match existing.read(&mut buf).await {
+            Ok(read) => read,
+            Err(err) => return Err(Error::CouldntReadMetadata(format!("{}", err))),
+        };
+        if 0 == read {
+            break;
+        };
+        md5_cx.consume(&buf[..read]);
+        for (_, state) in &mut hasher_states {
+            state.consume(&buf[..read]);
+        };
     };
+    Ok((md5_cx, hasher_states))
+}
 
-    for (_, content) in contents {
-        let ContentKind::File { link, md5, .. } = content.kind else {
-            return Err(Error::NotImplementedForSubdir);
+fn progress_bar_style() -> ProgressStyle {
+    ProgressStyle::with_template("{msg} [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+        .progress_chars("#>-")
+}
+
+fn download_all_child_contents<'a>(
+    api: &'a Api,
+    content: Content,
+    token: &'a str,
+    concurrency: usize,
+    dir: &'a Path,
+    max_retries: u32,
+    store: Option<&'a dyn StorageBackend>,
+    checksums: &'a [ChecksumAlgorithm],
+) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+    Box::pin(async move {
+        let ContentKind::Folder { contents, .. } = content.kind else {
+            return Err(Error::InvalidTopLevelFile(content.name));
         };
-        let digest = download_impl(link, content.name, token).await?;
-        if md5 != digest.0 {
-            return Err(Error::Md5DigestMismatched(format!("{:x} != {:x}", md5::Digest(md5), digest)));
+        let Some(contents) = contents else {
+            return Err(Error::NoContent);
         };
-    };
-    Ok(())
+        if 0 == contents.len() {
+            return Err(Error::NoContent);
+        };
+
+        if let Err(err) = create_dir_all(dir).await {
+            return Err(Error::DirectoryCouldntBeCreated(format!("{}", err)));
+        };
+
+        let multi_progress = MultiProgress::new();
+
+        let results: Vec<Result<(), Error>> = stream::iter(contents.into_iter())
+            .map(|(_, content)| {
+                let multi_progress = multi_progress.clone();
+                async move {
+                    match content.kind {
+                        ContentKind::File { link, md5, .. } => {
+                            let path = safe_child_path(dir, &content.name)?;
+                            let digests = download_impl(link, path.clone(), token, &multi_progress, max_retries, store, checksums).await?;
+                            if md5 != digests.md5.0 {
+                                return Err(Error::Md5DigestMismatched(format!("{:x} != {:x}", md5::Digest(md5), digests.md5)));
+                            };
+                            for (algorithm, hex_digest) in &digests.others {
+                                println!("{} {}  {}", algorithm, hex_digest, path.display());
+                            };
+                            Ok(())
+                        },
+                        ContentKind::Folder { .. } => {
+                            let child = api.get_content_by_id(content.id).await?;
+                            let child_dir = safe_child_path(dir, &content.name)?;
+                            download_all_child_contents(api, child, token, concurrency, &child_dir, max_retries, store, checksums).await
+                        },
+                    }
+                }
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+
+        for result in results {
+            result?;
+        };
+        Ok(())
+    })
 }
 
 fn get_token() -> Result<String, Error> {
@@ -296,26 +694,101 @@ fn get_token() -> Result<String, Error> {
 }
 
 
-struct Md5Filter<W: AsyncWrite> {
+/// The gofile-verified md5 plus any additional checksums requested via
+/// `--checksum`, as `(algorithm name, hex digest)` pairs.
+struct Digests {
+    md5: md5::Digest,
+    others: Vec<(&'static str, String)>,
+}
+
+enum HasherState {
+    Sha256(Sha256),
+}
+
+impl HasherState {
+    fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+        }
+    }
+
+    fn consume(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(bytes),
+        }
+    }
+}
+
+/// Generalizes the old md5-only filter: feeds every byte written through it
+/// to the gofile-required md5 hash plus whatever `--checksum` algorithms
+/// were requested, then reports all of them once writing completes.
+struct HashingWriter<W: AsyncWrite> {
     writer: W,
     md5_cx: md5::Context,
+    others: Vec<(&'static str, HasherState)>,
 }
 
-impl<W: AsyncWrite> Md5Filter<W> {
-    fn new(writer: W) -> Self {
-        Self { writer, md5_cx: md5::Context::new() }
+impl<W: AsyncWrite> HashingWriter<W> {
+    fn new(writer: W, checksums: &[ChecksumAlgorithm]) -> Self {
+        let others = checksums.iter().map(|algorithm| (algorithm.name(), HasherState::new(*algorithm))).collect();
+        Self::new_with_others(writer, md5::Context::new(), others)
     }
 
-    fn compute_digest(self) -> md5::Digest {
-        self.md5_cx.compute()
+    fn new_with_others(writer: W, md5_cx: md5::Context, others: Vec<(&'static str, HasherState)>) -> Self {
+        Self { writer, md5_cx, others }
+    }
+
+    fn compute_digests(self) -> Digests {
+        let md5 = self.md5_cx.compute();
+        let others = self.others.into_iter().map(|(name, state)| {
+            let hex_digest = match state {
+                HasherState::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            };
+            (name, hex_digest)
+        }).collect();
+        Digests { md5, others }
     }
 }
 
-impl<W: AsyncWrite + Unpin> AsyncWrite for Md5Filter<W> {
+impl<W: AsyncWrite + Unpin> AsyncWrite for HashingWriter<W> {
     fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
         match Pin::new(&mut self.writer).poll_write(cx, buf) {
             Poll::Ready(Ok(size)) => {
                 self.md5_cx.consume(&buf[..size]);
+                for (_, state) in &mut self.others {
+                    state.consume(&buf[..size]);
+                };
+                Poll::Ready(Ok(size))
+            },
+            other => other,
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().writer).poll_close(cx)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().writer).poll_flush(cx)
+    }
+}
+
+struct ProgressFilter<W: AsyncWrite> {
+    writer: W,
+    progress_bar: ProgressBar,
+}
+
+impl<W: AsyncWrite> ProgressFilter<W> {
+    fn new(writer: W, progress_bar: ProgressBar) -> Self {
+        Self { writer, progress_bar }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for ProgressFilter<W> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match Pin::new(&mut self.writer).poll_write(cx, buf) {
+            Poll::Ready(Ok(size)) => {
+                self.progress_bar.inc(size as u64);
                 Poll::Ready(Ok(size))
             },
             other => other,
@@ -0,0 +1,364 @@
+use std::{
+    error,
+    fmt::{
+        self,
+        Display,
+        Formatter,
+    },
+    future::Future,
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+use futures::io::AsyncWrite;
+
+use tokio::{
+    fs::{
+        File,
+        create_dir_all,
+    },
+    sync::{
+        mpsc,
+        oneshot,
+    },
+};
+
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+use aws_sdk_s3::{
+    Client,
+    types::{
+        CompletedMultipartUpload,
+        CompletedPart,
+    },
+    primitives::ByteStream,
+};
+
+const MULTIPART_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// How many parts may be queued ahead of the in-flight `upload_part` call.
+/// Bounds `MultipartUploadWriter`'s memory use to a small multiple of
+/// `MULTIPART_PART_SIZE` instead of growing without limit when S3 lags
+/// behind the incoming stream.
+const MAX_QUEUED_PARTS: usize = 2;
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io(String),
+    S3(String),
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl error::Error for StorageError {
+}
+
+/// A sink for downloaded content, abstracting over where the bytes end up
+/// (local disk, object storage, ...) so `download_impl` can stay agnostic
+/// to the destination.
+pub trait StorageBackend: Send + Sync {
+    fn create<'a>(&'a self, path: &'a Path) -> Pin<Box<dyn Future<Output = Result<Box<dyn AsyncWrite + Send + Unpin>, StorageError>> + Send + 'a>>;
+
+    fn finalize<'a>(&'a self, path: &'a Path) -> Pin<Box<dyn Future<Output = Result<(), StorageError>> + Send + 'a>>;
+}
+
+/// Matches the historical `File::create` behaviour: one file per logical path,
+/// rooted under `base_dir`.
+pub struct FileStore {
+    base_dir: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(base_dir: PathBuf) -> Self {
+        Self { base_dir }
+    }
+
+    fn full_path(&self, path: &Path) -> PathBuf {
+        self.base_dir.join(path)
+    }
+}
+
+impl StorageBackend for FileStore {
+    fn create<'a>(&'a self, path: &'a Path) -> Pin<Box<dyn Future<Output = Result<Box<dyn AsyncWrite + Send + Unpin>, StorageError>> + Send + 'a>> {
+        Box::pin(async move {
+            let full_path = self.full_path(path);
+            if let Some(parent) = full_path.parent() {
+                create_dir_all(parent).await.map_err(|err| StorageError::Io(format!("{}", err)))?;
+            };
+            let file = File::create(&full_path).await.map_err(|err| StorageError::Io(format!("{}", err)))?;
+            let writer: Box<dyn AsyncWrite + Send + Unpin> = Box::new(file.compat());
+            Ok(writer)
+        })
+    }
+
+    fn finalize<'a>(&'a self, _path: &'a Path) -> Pin<Box<dyn Future<Output = Result<(), StorageError>> + Send + 'a>> {
+        Box::pin(async move { Ok(()) })
+    }
+}
+
+/// Streams writes into an S3-compatible bucket using fixed-size multipart
+/// uploads, selectable via `--store s3://bucket/prefix`.
+pub struct ObjectStore {
+    client: Client,
+    bucket: String,
+    prefix: String,
+}
+
+impl ObjectStore {
+    pub async fn new(bucket: String, prefix: String) -> Self {
+        let config = aws_config::load_from_env().await;
+        let client = Client::new(&config);
+        Self { client, bucket, prefix }
+    }
+
+    fn key_for(&self, path: &Path) -> String {
+        let prefix = self.prefix.trim_end_matches('/');
+        if prefix.is_empty() {
+            path.display().to_string()
+        } else {
+            format!("{}/{}", prefix, path.display())
+        }
+    }
+}
+
+impl StorageBackend for ObjectStore {
+    fn create<'a>(&'a self, path: &'a Path) -> Pin<Box<dyn Future<Output = Result<Box<dyn AsyncWrite + Send + Unpin>, StorageError>> + Send + 'a>> {
+        Box::pin(async move {
+            let key = self.key_for(path);
+            let writer: Box<dyn AsyncWrite + Send + Unpin> = Box::new(MultipartUploadWriter::new(self.client.clone(), self.bucket.clone(), key));
+            Ok(writer)
+        })
+    }
+
+    fn finalize<'a>(&'a self, _path: &'a Path) -> Pin<Box<dyn Future<Output = Result<(), StorageError>> + Send + 'a>> {
+        Box::pin(async move { Ok(()) })
+    }
+}
+
+/// Sent over the part channel. The upload task only runs
+/// `complete_multipart_upload` once it has seen an explicit `Finish` — if the
+/// channel instead closes because the writer was dropped early (a
+/// mid-transfer error, a retry giving up on this attempt), that's
+/// indistinguishable from a plain disconnect, so the task aborts the
+/// multipart upload instead of completing a truncated object.
+enum PartMessage {
+    Data(Vec<u8>),
+    Finish,
+}
+
+/// A part send that is in flight against the bounded channel, kept around
+/// across `poll_write`/`poll_close` calls so it can be resumed instead of
+/// re-issued each time we're polled.
+type PendingSend = Pin<Box<dyn Future<Output = Result<(), mpsc::error::SendError<PartMessage>>> + Send>>;
+
+struct MultipartUploadWriter {
+    buffer: Vec<u8>,
+    part_sender: Option<mpsc::Sender<PartMessage>>,
+    pending_send: Option<PendingSend>,
+    finish_sent: bool,
+    done: oneshot::Receiver<io::Result<()>>,
+}
+
+impl MultipartUploadWriter {
+    fn new(client: Client, bucket: String, key: String) -> Self {
+        let (part_sender, part_receiver) = mpsc::channel(MAX_QUEUED_PARTS);
+        let (done_sender, done_receiver) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let result = run_multipart_upload(client, bucket, key, part_receiver).await;
+            let _ = done_sender.send(result);
+        });
+
+        Self { buffer: Vec::new(), part_sender: Some(part_sender), pending_send: None, finish_sent: false, done: done_receiver }
+    }
+
+    /// Drives `this.pending_send` (if any) and, once it clears, hands off
+    /// any further full parts sitting in `this.buffer` to the upload task.
+    /// Returns `Pending` as soon as the channel is full, without touching
+    /// `this.buffer` beyond what was already drained into a part, so the
+    /// caller's `buf` is never consumed on a `Pending` return.
+    fn drain_full_parts(this: &mut Self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            if let Some(pending) = &mut this.pending_send {
+                match pending.as_mut().poll(cx) {
+                    Poll::Ready(Ok(())) => this.pending_send = None,
+                    Poll::Ready(Err(_)) => return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, "upload task terminated"))),
+                    Poll::Pending => return Poll::Pending,
+                };
+            };
+
+            if this.buffer.len() < MULTIPART_PART_SIZE {
+                return Poll::Ready(Ok(()));
+            };
+
+            let part = this.buffer.drain(..MULTIPART_PART_SIZE).collect();
+            let Some(part_sender) = this.part_sender.clone() else {
+                return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, "upload task already finished")));
+            };
+            this.pending_send = Some(Box::pin(async move { part_sender.send(PartMessage::Data(part)).await }));
+        };
+    }
+}
+
+/// Best-effort cleanup for a multipart upload that won't be completed, so a
+/// failed or abandoned attempt doesn't leave storage billed for orphaned
+/// parts forever. Errors here are swallowed in favour of the original error
+/// that triggered the abort.
+async fn abort_multipart_upload(client: &Client, bucket: &str, key: &str, upload_id: &str) {
+    let _ = client.abort_multipart_upload()
+        .bucket(bucket)
+        .key(key)
+        .upload_id(upload_id)
+        .send()
+        .await;
+}
+
+async fn run_multipart_upload(client: Client, bucket: String, key: String, mut parts: mpsc::Receiver<PartMessage>) -> io::Result<()> {
+    let create_output = client.create_multipart_upload()
+        .bucket(&bucket)
+        .key(&key)
+        .send()
+        .await
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{}", err)))?;
+    let upload_id = create_output.upload_id()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "missing upload id"))?
+        .to_string();
+
+    let mut completed_parts = Vec::new();
+    let mut part_number = 1;
+    let mut finished = false;
+
+    while let Some(message) = parts.recv().await {
+        let chunk = match message {
+            PartMessage::Data(chunk) => chunk,
+            PartMessage::Finish => {
+                finished = true;
+                break;
+            },
+        };
+        let upload_part_output = match client.upload_part()
+            .bucket(&bucket)
+            .key(&key)
+            .upload_id(&upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(chunk))
+            .send()
+            .await
+        {
+            Ok(output) => output,
+            Err(err) => {
+                abort_multipart_upload(&client, &bucket, &key, &upload_id).await;
+                return Err(io::Error::new(io::ErrorKind::Other, format!("{}", err)));
+            },
+        };
+        let e_tag = upload_part_output.e_tag().unwrap_or_default().to_string();
+        completed_parts.push(CompletedPart::builder().part_number(part_number).e_tag(e_tag).build());
+        part_number += 1;
+    };
+
+    if !finished {
+        // The writer was dropped before sending `Finish` (a mid-transfer
+        // error, or a retry abandoning this attempt) - there is no complete
+        // object to assemble, so abort rather than finalize a partial one.
+        abort_multipart_upload(&client, &bucket, &key, &upload_id).await;
+        return Err(io::Error::new(io::ErrorKind::Other, "writer dropped before upload finished"));
+    };
+
+    if let Err(err) = client.complete_multipart_upload()
+        .bucket(&bucket)
+        .key(&key)
+        .upload_id(&upload_id)
+        .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(completed_parts)).build())
+        .send()
+        .await
+    {
+        abort_multipart_upload(&client, &bucket, &key, &upload_id).await;
+        return Err(io::Error::new(io::ErrorKind::Other, format!("{}", err)));
+    };
+
+    Ok(())
+}
+
+impl AsyncWrite for MultipartUploadWriter {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        // Flush out any full parts (and any send already in flight) before
+        // buffering more bytes, so a lagging upload task is felt here as
+        // genuine backpressure rather than an ever-growing buffer.
+        match Self::drain_full_parts(this, cx) {
+            Poll::Ready(Ok(())) => {},
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        this.buffer.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        Self::drain_full_parts(this, cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        match Self::drain_full_parts(this, cx) {
+            Poll::Ready(Ok(())) => {},
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        if !this.buffer.is_empty() {
+            let remaining = std::mem::take(&mut this.buffer);
+            let Some(part_sender) = this.part_sender.clone() else {
+                return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, "upload task already finished")));
+            };
+            let mut pending: PendingSend = Box::pin(async move { part_sender.send(PartMessage::Data(remaining)).await });
+            match pending.as_mut().poll(cx) {
+                Poll::Ready(Ok(())) => {},
+                Poll::Ready(Err(_)) => return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, "upload task terminated"))),
+                Poll::Pending => {
+                    this.pending_send = Some(pending);
+                    return Poll::Pending;
+                },
+            };
+        };
+
+        if !this.finish_sent {
+            this.finish_sent = true;
+            let Some(part_sender) = this.part_sender.clone() else {
+                return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, "upload task already finished")));
+            };
+            let mut pending: PendingSend = Box::pin(async move { part_sender.send(PartMessage::Finish).await });
+            match pending.as_mut().poll(cx) {
+                Poll::Ready(Ok(())) => {},
+                Poll::Ready(Err(_)) => return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, "upload task terminated"))),
+                Poll::Pending => {
+                    this.pending_send = Some(pending);
+                    return Poll::Pending;
+                },
+            };
+        };
+
+        this.part_sender = None;
+
+        Pin::new(&mut this.done).poll(cx).map(|result| match result {
+            Ok(upload_result) => upload_result,
+            Err(_) => Err(io::Error::new(io::ErrorKind::Other, "upload task dropped")),
+        })
+    }
+}